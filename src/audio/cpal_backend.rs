@@ -0,0 +1,342 @@
+use super::{AudioBackend, AudioSource};
+use crate::demux;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::{Decoder, Source};
+use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How many samples a `Pcm`/`File` source's lazy sample iterator is chunked into per
+/// `produce()` call. Container sources instead produce one chunk per decoded ffmpeg
+/// audio frame, whatever size that happens to be.
+const CHUNK_SAMPLES: usize = 4096;
+
+/// A producer/consumer queue of decoded PCM chunks. The producer thread `produce()`s
+/// whole chunks as they're decoded; the cpal data callback `consume()`s out of the
+/// front of the queue, so it never has to know about chunk boundaries - only whether
+/// enough samples are available yet.
+struct PcmBuffers {
+    buffers: Vec<Vec<f32>>,
+    consumer_cursor: usize,
+}
+
+impl PcmBuffers {
+    fn new() -> Self {
+        PcmBuffers {
+            buffers: Vec::new(),
+            consumer_cursor: 0,
+        }
+    }
+
+    fn produce(&mut self, chunk: Vec<f32>) {
+        // a zero-length chunk would sit at the back of the queue with nothing left
+        // for consume() to take, so the queue could never report empty
+        if !chunk.is_empty() {
+            self.buffers.push(chunk);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Total samples currently queued and not yet handed to the consumer.
+    fn available(&self) -> usize {
+        self.buffers
+            .iter()
+            .enumerate()
+            .map(|(i, buf)| if i == 0 { buf.len() - self.consumer_cursor } else { buf.len() })
+            .sum()
+    }
+
+    /// Copies up to `data.len()` samples out of the queue, returning how many were
+    /// written. Normally this only ever copies a full `data.len()` or nothing at all
+    /// (the caller is expected to emit silence for the gap on a partial underrun) -
+    /// but once the producer is done (`drain_partial`), a trailing remainder smaller
+    /// than one callback's worth is drained anyway, since it will never grow into a
+    /// full `data.len()` again and holding onto it would leave the queue non-empty
+    /// forever.
+    fn consume(&mut self, data: &mut [f32], drain_partial: bool) -> usize {
+        let available = self.available();
+        let to_take = if available >= data.len() {
+            data.len()
+        } else if drain_partial {
+            available
+        } else {
+            return 0;
+        };
+
+        let mut written = 0;
+        while written < to_take {
+            let front = &self.buffers[0];
+            let front_remaining = front.len() - self.consumer_cursor;
+            let take = front_remaining.min(to_take - written);
+            data[written..written + take]
+                .copy_from_slice(&front[self.consumer_cursor..self.consumer_cursor + take]);
+            self.consumer_cursor += take;
+            written += take;
+
+            if self.consumer_cursor == front.len() {
+                self.buffers.remove(0);
+                self.consumer_cursor = 0;
+            }
+        }
+        written
+    }
+}
+
+/// The precise-clock backend: a producer thread decodes PCM into a `PcmBuffers` queue,
+/// and the cpal output stream's data callback is the consumer. `position()` is derived
+/// from a monotonically increasing consumed-sample counter updated by that callback, so
+/// it reflects what the DAC has actually played rather than a free-running timer - far
+/// more accurate than rodio's own clock.
+pub struct CpalBackend {
+    source: Option<AudioSource>,
+    stream: Option<cpal::Stream>,
+    buffers: Arc<Mutex<PcmBuffers>>,
+    consumed_samples: Arc<Mutex<u64>>,
+    producer_done: Arc<AtomicBool>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl CpalBackend {
+    pub fn new(source: AudioSource) -> Self {
+        CpalBackend {
+            source: Some(source),
+            stream: None,
+            buffers: Arc::new(Mutex::new(PcmBuffers::new())),
+            consumed_samples: Arc::new(Mutex::new(0)),
+            producer_done: Arc::new(AtomicBool::new(false)),
+            sample_rate: 48_000,
+            channels: 2,
+        }
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    fn start(&mut self) -> Result<(), String> {
+        let source = self
+            .source
+            .take()
+            .ok_or("CpalBackend::start() called twice")?;
+
+        let producer_buffers = self.buffers.clone();
+        let producer_done = self.producer_done.clone();
+
+        match source {
+            AudioSource::Pcm {
+                samples,
+                sample_rate,
+                channels,
+            } => {
+                self.sample_rate = sample_rate;
+                self.channels = channels;
+                spawn_chunked_producer(samples.into_iter(), producer_buffers, producer_done)?;
+            }
+            AudioSource::File(path) => {
+                let file = File::open(&path)
+                    .map_err(|err| format!("Failed to open audio file!\nError: {:?}", err))?;
+                let decoder = Decoder::try_from(file)
+                    .map_err(|err| format!("Failed to decode audio file!\nError: {:?}", err))?;
+                self.sample_rate = decoder.sample_rate();
+                self.channels = decoder.channels();
+                spawn_chunked_producer(decoder.convert_samples(), producer_buffers, producer_done)?;
+            }
+            AudioSource::Container {
+                input_file,
+                sample_rate,
+                channels,
+            } => {
+                // decode+resample incrementally on the producer thread itself, chunk
+                // by chunk as ffmpeg hands us frames, instead of materializing the
+                // whole track before playback can start
+                self.sample_rate = sample_rate;
+                self.channels = channels;
+                thread::Builder::new()
+                    .name("bad_apple_audio_producer".to_string())
+                    .spawn(move || {
+                        let result = demux::decode_audio_chunks(&input_file, sample_rate, |chunk| {
+                            producer_buffers.lock().unwrap().produce(chunk);
+                        });
+                        if let Err(err) = result {
+                            eprintln!("[CpalBackend]: Container audio decode failed: {}", err);
+                        }
+                        producer_done.store(true, Ordering::Relaxed);
+                    })
+                    .map_err(|err| format!("Failed to spawn audio producer thread: {:?}", err))?;
+            }
+        }
+
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or("no cpal output device available")?;
+        let config = cpal::StreamConfig {
+            channels: self.channels,
+            sample_rate: cpal::SampleRate(self.sample_rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let consumer_buffers = self.buffers.clone();
+        let consumed_samples = self.consumed_samples.clone();
+        let consumer_producer_done = self.producer_done.clone();
+
+        let stream = device
+            .build_output_stream(
+                &config,
+                move |data: &mut [f32], _info: &cpal::OutputCallbackInfo| {
+                    // once the producer is done, drain whatever's left even if it's a
+                    // partial callback's worth, so the queue can actually empty out
+                    let done = consumer_producer_done.load(Ordering::Relaxed);
+                    let written = consumer_buffers.lock().unwrap().consume(data, done);
+                    if written > 0 {
+                        *consumed_samples.lock().unwrap() += written as u64;
+                    }
+                    if written < data.len() {
+                        // underrun (or a final partial drain): pad the rest with silence
+                        // instead of glitching on stale samples
+                        for sample in data[written..].iter_mut() {
+                            *sample = 0.0;
+                        }
+                    }
+                },
+                |err| eprintln!("[CpalBackend]: Output stream error: {:?}", err),
+                None,
+            )
+            .map_err(|err| format!("Failed to build cpal output stream: {:?}", err))?;
+
+        stream
+            .play()
+            .map_err(|err| format!("Failed to start cpal stream: {:?}", err))?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn position(&self) -> Duration {
+        let consumed_samples = *self.consumed_samples.lock().unwrap();
+        let consumed_frames = consumed_samples / self.channels.max(1) as u64;
+        Duration::from_secs_f64(consumed_frames as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    fn pause(&mut self) {
+        if let Some(stream) = &self.stream {
+            let _ = stream.pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(stream) = &self.stream {
+            let _ = stream.play();
+        }
+    }
+
+    fn stop(&mut self) {
+        self.stream = None;
+    }
+
+    fn wait_until_end(&self) {
+        // block until the producer has hit EOF *and* every chunk it produced has
+        // been drained by the consumer - a sustained underrun near the end of the
+        // track (consumed_samples frozen while the callback emits silence) must not
+        // be mistaken for "finished", so don't infer completion from counter
+        // stagnation alone
+        loop {
+            let done = self.producer_done.load(Ordering::Relaxed);
+            let drained = self.buffers.lock().unwrap().is_empty();
+            if done && drained {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+fn spawn_chunked_producer<I>(
+    mut samples: I,
+    buffers: Arc<Mutex<PcmBuffers>>,
+    producer_done: Arc<AtomicBool>,
+) -> Result<(), String>
+where
+    I: Iterator<Item = f32> + Send + 'static,
+{
+    thread::Builder::new()
+        .name("bad_apple_audio_producer".to_string())
+        .spawn(move || {
+            loop {
+                let chunk: Vec<f32> = (&mut samples).take(CHUNK_SAMPLES).collect();
+                if chunk.is_empty() {
+                    break;
+                }
+                buffers.lock().unwrap().produce(chunk);
+            }
+            producer_done.store(true, Ordering::Relaxed);
+        })
+        .map_err(|err| format!("Failed to spawn audio producer thread: {:?}", err))?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::PcmBuffers;
+
+    #[test]
+    fn consume_exact_returns_full_chunk_when_enough_is_buffered() {
+        let mut buffers = PcmBuffers::new();
+        buffers.produce(vec![1.0, 2.0, 3.0, 4.0]);
+
+        let mut out = [0.0; 4];
+        let written = buffers.consume(&mut out, false);
+
+        assert_eq!(written, 4);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+        assert!(buffers.is_empty());
+    }
+
+    #[test]
+    fn consume_exact_spans_multiple_produced_chunks_of_uneven_size() {
+        let mut buffers = PcmBuffers::new();
+        buffers.produce(vec![1.0, 2.0, 3.0]);
+        buffers.produce(vec![4.0]);
+        buffers.produce(vec![5.0, 6.0]);
+
+        let mut out = [0.0; 4];
+        assert_eq!(buffers.consume(&mut out, false), 4);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+        assert!(!buffers.is_empty());
+
+        let mut out = [0.0; 2];
+        assert_eq!(buffers.consume(&mut out, false), 2);
+        assert_eq!(out, [5.0, 6.0]);
+        assert!(buffers.is_empty());
+    }
+
+    #[test]
+    fn consume_exact_refuses_a_short_read_while_producer_is_still_running() {
+        let mut buffers = PcmBuffers::new();
+        buffers.produce(vec![1.0, 2.0]);
+
+        let mut out = [0.0; 4];
+        assert_eq!(buffers.consume(&mut out, false), 0);
+        assert!(!buffers.is_empty());
+    }
+
+    #[test]
+    fn consume_drains_a_non_aligned_remainder_once_the_producer_is_done() {
+        // regression test: with the producer done, a trailing chunk smaller than one
+        // callback's worth used to never satisfy consume_exact's "enough buffered"
+        // check, so the queue could never empty and wait_until_end spun forever
+        let mut buffers = PcmBuffers::new();
+        buffers.produce(vec![1.0, 2.0]);
+
+        let mut out = [0.0; 4];
+        let written = buffers.consume(&mut out, true);
+
+        assert_eq!(written, 2);
+        assert_eq!(&out[..2], &[1.0, 2.0]);
+        assert!(buffers.is_empty());
+    }
+}
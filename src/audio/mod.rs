@@ -0,0 +1,52 @@
+// Pluggable audio playback, so the GFX loop's timing needs (it only ever wants
+// `position()`) are decoupled from how a given backend actually gets sound out of the
+// box - rodio, cpal, or nothing at all for headless/CI runs. Modeled in spirit on
+// Ruffle's `AudioBackend` abstraction.
+
+mod cpal_backend;
+mod null_backend;
+mod rodio_backend;
+
+pub use cpal_backend::CpalBackend;
+pub use null_backend::NullBackend;
+pub use rodio_backend::RodioBackend;
+
+use std::time::Duration;
+
+/// Where the decoded audio for a backend to play comes from.
+pub enum AudioSource {
+    /// An already-demuxed audio file on disk (frame input mode's `music.mp3`)
+    File(String),
+    /// Raw interleaved PCM, already decoded/resampled
+    Pcm {
+        samples: Vec<f32>,
+        sample_rate: u32,
+        channels: u16,
+    },
+    /// A media container whose audio stream hasn't been decoded yet (container input
+    /// mode). Decoding is left to the backend so it can be done incrementally rather
+    /// than blocking on the whole track before playback starts.
+    Container {
+        input_file: String,
+        sample_rate: u32,
+        channels: u16,
+    },
+}
+
+/// A source of audio playback the GFX loop can sync its frame timing against.
+pub trait AudioBackend {
+    /// Open the output device (if any) and begin playback.
+    fn start(&mut self) -> Result<(), String>;
+
+    /// Elapsed playback position, used by the audio-master-clock sync to compute
+    /// the ideal frame index.
+    fn position(&self) -> Duration;
+
+    fn pause(&mut self);
+    fn resume(&mut self);
+    fn stop(&mut self);
+
+    /// Block the calling thread until this backend has finished playing out,
+    /// best-effort outside of rodio (which has a native `sleep_until_end`).
+    fn wait_until_end(&self);
+}
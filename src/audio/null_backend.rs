@@ -0,0 +1,107 @@
+use super::AudioBackend;
+use std::time::{Duration, Instant};
+
+/// Advances a synthetic clock with no real audio device involved, so the player can
+/// be exercised headlessly (CI, benchmarking) without a sound card.
+pub struct NullBackend {
+    expected_duration: Duration,
+    started_at: Option<Instant>,
+    paused_at: Option<Instant>,
+    /// Total time spent in completed pauses so far (a pause still in progress is
+    /// covered by `paused_at`), so `position()` can subtract it back out of
+    /// `start.elapsed()` instead of letting the clock jump forward by however long
+    /// the last pause lasted.
+    paused_accum: Duration,
+}
+
+impl NullBackend {
+    /// `expected_duration` is how long the synthetic clock should run for before
+    /// `wait_until_end` returns - typically `total_frames / framerate`.
+    pub fn new(expected_duration: Duration) -> Self {
+        NullBackend {
+            expected_duration,
+            started_at: None,
+            paused_at: None,
+            paused_accum: Duration::ZERO,
+        }
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn start(&mut self) -> Result<(), String> {
+        self.started_at = Some(Instant::now());
+        Ok(())
+    }
+
+    fn position(&self) -> Duration {
+        match self.started_at {
+            Some(start) => match self.paused_at {
+                Some(paused) => paused.saturating_duration_since(start) - self.paused_accum,
+                None => start.elapsed() - self.paused_accum,
+            },
+            None => Duration::ZERO,
+        }
+    }
+
+    fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(paused) = self.paused_at.take() {
+            self.paused_accum += paused.elapsed();
+        }
+    }
+
+    fn stop(&mut self) {
+        self.started_at = None;
+        self.paused_at = None;
+        self.paused_accum = Duration::ZERO;
+    }
+
+    fn wait_until_end(&self) {
+        let elapsed = self.position();
+        if elapsed < self.expected_duration {
+            std::thread::sleep(self.expected_duration - elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_is_zero_before_start() {
+        let backend = NullBackend::new(Duration::from_secs(1));
+        assert_eq!(backend.position(), Duration::ZERO);
+    }
+
+    #[test]
+    fn position_freezes_while_paused() {
+        let mut backend = NullBackend::new(Duration::from_secs(1));
+        backend.start().unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        backend.pause();
+
+        let frozen = backend.position();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(backend.position(), frozen);
+    }
+
+    #[test]
+    fn resume_does_not_count_the_paused_interval() {
+        let mut backend = NullBackend::new(Duration::from_secs(1));
+        backend.start().unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        backend.pause();
+        std::thread::sleep(Duration::from_millis(100));
+        backend.resume();
+
+        // the 100ms spent paused must not show up in position() - only a little
+        // more than the 20ms that elapsed before the pause
+        assert!(backend.position() < Duration::from_millis(80));
+    }
+}
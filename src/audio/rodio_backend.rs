@@ -0,0 +1,97 @@
+use super::{AudioBackend, AudioSource};
+use crate::demux;
+use rodio::{Decoder, OutputStream, Sink};
+use std::fs::File;
+use std::time::Duration;
+
+/// Wraps the player's original rodio-based playback: a file on disk, or raw PCM
+/// handed to rodio via a `SamplesBuffer` source.
+pub struct RodioBackend {
+    source: AudioSource,
+    sink: Option<Sink>,
+    stream: Option<OutputStream>,
+}
+
+impl RodioBackend {
+    pub fn new(source: AudioSource) -> Self {
+        RodioBackend {
+            source,
+            sink: None,
+            stream: None,
+        }
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn start(&mut self) -> Result<(), String> {
+        let stream_handle = rodio::OutputStreamBuilder::open_default_stream()
+            .map_err(|err| format!("Failed to open default audio stream!\nError: {:?}", err))?;
+        let sink = rodio::Sink::connect_new(stream_handle.mixer());
+
+        match &self.source {
+            AudioSource::File(path) => {
+                let file = File::open(path)
+                    .map_err(|err| format!("Failed to open audio file!\nError: {:?}", err))?;
+                let decoded = Decoder::try_from(file)
+                    .map_err(|err| format!("Failed to play audio file!\nError: {:?}", err))?;
+                sink.append(decoded);
+            }
+            AudioSource::Pcm {
+                samples,
+                sample_rate,
+                channels,
+            } => {
+                let buffer =
+                    rodio::buffer::SamplesBuffer::new(*channels, *sample_rate, samples.clone());
+                sink.append(buffer);
+            }
+            AudioSource::Container {
+                input_file,
+                sample_rate,
+                channels,
+            } => {
+                // rodio has no incremental-decode hook, so materialize the whole track
+                // up front, same as it always has for container input mode
+                let pcm = demux::decode_audio_to_pcm(input_file, *sample_rate)
+                    .map_err(|err| format!("Failed to decode container audio: {}", err))?;
+                let buffer = rodio::buffer::SamplesBuffer::new(*channels, *sample_rate, pcm);
+                sink.append(buffer);
+            }
+        }
+
+        self.sink = Some(sink);
+        self.stream = Some(stream_handle);
+        Ok(())
+    }
+
+    fn position(&self) -> Duration {
+        self.sink
+            .as_ref()
+            .map(|sink| sink.get_pos())
+            .unwrap_or_default()
+    }
+
+    fn pause(&mut self) {
+        if let Some(sink) = &self.sink {
+            sink.pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(sink) = &self.sink {
+            sink.play();
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(sink) = &self.sink {
+            sink.stop();
+        }
+    }
+
+    fn wait_until_end(&self) {
+        if let Some(sink) = &self.sink {
+            sink.sleep_until_end();
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// In-process demuxing/decoding of a single media container (mp4/mkv/...) via ffmpeg-next,
+// so the player can run directly off a video file instead of a directory of pre-extracted
+// `{:03}.jpg` frames plus a separate `music.mp3`.
+
+use crossbeam::channel;
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::format::Pixel;
+use ffmpeg_next::media::Type as MediaType;
+use ffmpeg_next::software::resampling;
+use ffmpeg_next::software::scaling;
+use image::{ImageBuffer, Rgb};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+/// Maps a `QualityController` level to the scaler algorithm used to resize decoded
+/// frames to display size - mirrors frame-directory mode's `FilterType` backoff
+/// (Triangle -> Nearest -> half-res Nearest), but for the single demux decode thread
+/// there's no separate "decode at half resolution" step worth the complexity, so the
+/// degradation is entirely in the scaling algorithm's cost.
+fn scaler_flags_for_quality(quality: u8) -> scaling::Flags {
+    match quality {
+        0 => scaling::Flags::BILINEAR,
+        1 => scaling::Flags::FAST_BILINEAR,
+        _ => scaling::Flags::POINT,
+    }
+}
+
+/// Everything the caller needs to know about a container before it starts decoding it,
+/// so CLI args like `--total-frames`/`--frame-format` can stay optional in container mode.
+pub struct ContainerInfo {
+    pub video_frame_cnt: Option<usize>,
+    pub framerate: f64,
+}
+
+/// Wraps the ffmpeg video decoder together with the scaler that resizes/converts its
+/// output to the RGB24 buffers the rest of the player already knows how to draw.
+struct DecodeContext {
+    decoder: ffmpeg::codec::decoder::Video,
+    scaler: scaling::Context,
+    scaler_quality: u8,
+}
+
+/// Probe a container for its best video stream's frame count (if known) and framerate,
+/// without decoding anything.
+pub fn probe(input_file: &str) -> Result<ContainerInfo, String> {
+    let ctx = ffmpeg::format::input(input_file)
+        .map_err(|err| format!("Failed to open container {}: {:?}", input_file, err))?;
+
+    let stream = ctx
+        .streams()
+        .best(MediaType::Video)
+        .ok_or_else(|| format!("{} has no video stream", input_file))?;
+
+    let rational = stream.avg_frame_rate();
+    let framerate = if rational.denominator() != 0 {
+        rational.numerator() as f64 / rational.denominator() as f64
+    } else {
+        0.0
+    };
+
+    let video_frame_cnt = if stream.frames() > 0 {
+        Some(stream.frames() as usize)
+    } else {
+        None
+    };
+
+    Ok(ContainerInfo {
+        video_frame_cnt,
+        framerate,
+    })
+}
+
+/// Demux and decode the video stream of `input_file`, scaling every frame to
+/// `disp_w x disp_h` RGB24 and pushing it onto `tx` - the same channel type
+/// `start_img_thread` feeds in frame-directory mode, so the GFX loop is unchanged.
+/// Each frame's presentation timestamp (in seconds) is pushed in lockstep onto
+/// `pts_tx`, so the GFX loop can match frames against the audio clock by real
+/// timestamp instead of an assumed-constant-framerate frame index. `quality` is the
+/// same shared level `QualityController` drives for frame-directory mode's image
+/// threads, here steering the scaler's algorithm instead of a resize target.
+pub fn spawn_video_decode_thread(
+    input_file: String,
+    disp_w: u32,
+    disp_h: u32,
+    tx: channel::Sender<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    pts_tx: channel::Sender<f64>,
+    quality: Arc<AtomicU8>,
+) -> Result<JoinHandle<()>, std::io::Error> {
+    thread::Builder::new()
+        .name("bad_apple_demux_video".to_string())
+        .spawn(move || {
+            if let Err(err) = run_video_decode(&input_file, disp_w, disp_h, &tx, &pts_tx, &quality)
+            {
+                eprintln!("[Demux Video Thread]: {}", err);
+            }
+            println!("[Demux Video Thread]: Stopped!");
+        })
+}
+
+fn run_video_decode(
+    input_file: &str,
+    disp_w: u32,
+    disp_h: u32,
+    tx: &channel::Sender<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    pts_tx: &channel::Sender<f64>,
+    quality: &Arc<AtomicU8>,
+) -> Result<(), String> {
+    let mut ictx = ffmpeg::format::input(input_file)
+        .map_err(|err| format!("Failed to open container: {:?}", err))?;
+
+    let stream = ictx
+        .streams()
+        .best(MediaType::Video)
+        .ok_or("no video stream found")?;
+    let stream_idx = stream.index();
+    let time_base = stream.time_base();
+
+    let decoder_ctx = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+        .map_err(|err| format!("Failed to build video decoder context: {:?}", err))?;
+    let decoder = decoder_ctx
+        .decoder()
+        .video()
+        .map_err(|err| format!("Failed to open video decoder: {:?}", err))?;
+
+    let initial_quality = quality.load(Ordering::Relaxed);
+    let scaler = scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::RGB24,
+        disp_w,
+        disp_h,
+        scaler_flags_for_quality(initial_quality),
+    )
+    .map_err(|err| format!("Failed to build video scaler: {:?}", err))?;
+
+    let mut ctx = DecodeContext {
+        decoder,
+        scaler,
+        scaler_quality: initial_quality,
+    };
+
+    println!("[Demux Video Thread]: Started!");
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != stream_idx {
+            continue;
+        }
+        let quality_level = quality.load(Ordering::Relaxed);
+        if quality_level != ctx.scaler_quality {
+            ctx.scaler = scaling::Context::get(
+                ctx.decoder.format(),
+                ctx.decoder.width(),
+                ctx.decoder.height(),
+                Pixel::RGB24,
+                disp_w,
+                disp_h,
+                scaler_flags_for_quality(quality_level),
+            )
+            .map_err(|err| format!("Failed to rebuild video scaler: {:?}", err))?;
+            ctx.scaler_quality = quality_level;
+        }
+
+        ctx.decoder
+            .send_packet(&packet)
+            .map_err(|err| format!("Failed to send video packet: {:?}", err))?;
+        drain_video_frames(&mut ctx, disp_w, disp_h, time_base, tx, pts_tx)?;
+    }
+    ctx.decoder
+        .send_eof()
+        .map_err(|err| format!("Failed to flush video decoder: {:?}", err))?;
+    drain_video_frames(&mut ctx, disp_w, disp_h, time_base, tx, pts_tx)?;
+    Ok(())
+}
+
+fn drain_video_frames(
+    ctx: &mut DecodeContext,
+    disp_w: u32,
+    disp_h: u32,
+    time_base: ffmpeg::Rational,
+    tx: &channel::Sender<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    pts_tx: &channel::Sender<f64>,
+) -> Result<(), String> {
+    let mut decoded = ffmpeg::util::frame::Video::empty();
+    while ctx.decoder.receive_frame(&mut decoded).is_ok() {
+        // convert the frame's presentation timestamp from stream time_base units to
+        // seconds so the GFX loop can compare it directly against the audio clock
+        let pts_secs = decoded
+            .pts()
+            .map(|pts| pts as f64 * time_base.numerator() as f64 / time_base.denominator() as f64)
+            .unwrap_or(0.0);
+
+        let mut scaled = ffmpeg::util::frame::Video::empty();
+        ctx.scaler
+            .run(&decoded, &mut scaled)
+            .map_err(|err| format!("Failed to scale video frame: {:?}", err))?;
+
+        let mut rgb = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(disp_w, disp_h);
+        let stride = scaled.stride(0);
+        let data = scaled.data(0);
+        for y in 0..disp_h as usize {
+            let row = &data[y * stride..y * stride + disp_w as usize * 3];
+            let dst_row = &mut rgb.as_mut()[y * disp_w as usize * 3..(y + 1) * disp_w as usize * 3];
+            dst_row.copy_from_slice(row);
+        }
+
+        // pts first: the GFX loop pulls these in lockstep with the image itself
+        pts_tx
+            .send(pts_secs)
+            .map_err(|err| format!("Failed to send frame pts through channel: {:?}", err))?;
+        tx.send(rgb)
+            .map_err(|err| format!("Failed to send decoded frame through channel: {:?}", err))?;
+    }
+    Ok(())
+}
+
+/// Demux and decode the audio stream of `input_file`, resampling it to interleaved
+/// f32 stereo at `sample_rate`, handing each resampled frame's samples to `on_chunk`
+/// as soon as it's decoded - so a caller driving this from its own producer thread
+/// (e.g. `CpalBackend`) gets real incremental decode work instead of a single
+/// eagerly-materialized buffer.
+pub fn decode_audio_chunks<F: FnMut(Vec<f32>)>(
+    input_file: &str,
+    sample_rate: u32,
+    mut on_chunk: F,
+) -> Result<(), String> {
+    let mut ictx = ffmpeg::format::input(input_file)
+        .map_err(|err| format!("Failed to open container: {:?}", err))?;
+
+    let stream = ictx
+        .streams()
+        .best(MediaType::Audio)
+        .ok_or("no audio stream found")?;
+    let stream_idx = stream.index();
+
+    let decoder_ctx = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+        .map_err(|err| format!("Failed to build audio decoder context: {:?}", err))?;
+    let mut decoder = decoder_ctx
+        .decoder()
+        .audio()
+        .map_err(|err| format!("Failed to open audio decoder: {:?}", err))?;
+
+    let mut resampler = resampling::Context::get(
+        decoder.format(),
+        decoder.channel_layout(),
+        decoder.rate(),
+        ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+        ffmpeg::util::channel_layout::ChannelLayout::STEREO,
+        sample_rate,
+    )
+    .map_err(|err| format!("Failed to build audio resampler: {:?}", err))?;
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != stream_idx {
+            continue;
+        }
+        decoder
+            .send_packet(&packet)
+            .map_err(|err| format!("Failed to send audio packet: {:?}", err))?;
+        drain_audio_frames(&mut decoder, &mut resampler, &mut on_chunk)?;
+    }
+    decoder
+        .send_eof()
+        .map_err(|err| format!("Failed to flush audio decoder: {:?}", err))?;
+    drain_audio_frames(&mut decoder, &mut resampler, &mut on_chunk)?;
+
+    Ok(())
+}
+
+/// Same as `decode_audio_chunks`, but collects everything into one buffer - for
+/// backends (like `RodioBackend`) that need the whole track up front.
+pub fn decode_audio_to_pcm(input_file: &str, sample_rate: u32) -> Result<Vec<f32>, String> {
+    let mut pcm = Vec::new();
+    decode_audio_chunks(input_file, sample_rate, |chunk| pcm.extend_from_slice(&chunk))?;
+    Ok(pcm)
+}
+
+fn drain_audio_frames<F: FnMut(Vec<f32>)>(
+    decoder: &mut ffmpeg::codec::decoder::Audio,
+    resampler: &mut resampling::Context,
+    on_chunk: &mut F,
+) -> Result<(), String> {
+    let mut decoded = ffmpeg::util::frame::Audio::empty();
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        let mut resampled = ffmpeg::util::frame::Audio::empty();
+        resampler
+            .run(&decoded, &mut resampled)
+            .map_err(|err| format!("Failed to resample audio frame: {:?}", err))?;
+
+        let samples = resampled.samples() * resampled.channels() as usize;
+        let data = resampled.data(0);
+        let floats: &[f32] =
+            unsafe { std::slice::from_raw_parts(data.as_ptr() as *const f32, samples) };
+        on_chunk(floats.to_vec());
+    }
+    Ok(())
+}
@@ -1,10 +1,17 @@
 // Crates
 extern crate clap;
+extern crate cpal;
 extern crate crossbeam;
+extern crate ffmpeg_next;
 extern crate framebuffer;
 extern crate image;
 extern crate rodio;
 
+// Audio module (pluggable AudioBackend: rodio, cpal, null)
+mod audio;
+// Demux module (in-process container demuxing via ffmpeg-next)
+mod demux;
+
 // Clap crate
 use clap::Parser;
 
@@ -24,16 +31,148 @@ use image::imageops::FilterType;
 use image::ImageReader;
 use image::RgbImage;
 
-// Rodio crate
-use rodio::{Decoder, OutputStream, Sink};
+// Audio module
+use audio::{AudioBackend, AudioSource, CpalBackend, NullBackend, RodioBackend};
 
 // Standard crate
-use std::fs::File;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::vec::Vec;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum InputMode {
+    /// A directory of pre-extracted `{:03}.jpg` frames plus a `music.mp3`
+    Frames,
+    /// A single media file (mp4/mkv/...), demuxed and decoded in-process
+    Container,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum SyncMode {
+    /// Drive the render loop off the audio playback clock
+    Audio,
+    /// Drive the render loop off a free-running wall-clock timer (legacy behavior)
+    Wallclock,
+}
+
+/// Tracks how far the render loop has drifted from the audio clock over a run.
+/// `frames_dropped` is shared with the image threads so their periodic timing
+/// printouts can surface it too.
+struct DriftStats {
+    frames_dropped: Arc<AtomicUsize>,
+    max_drift_frames: i64,
+    samples: usize,
+    drift_sum_frames: i64,
+}
+
+impl DriftStats {
+    fn new(frames_dropped: Arc<AtomicUsize>) -> Self {
+        DriftStats {
+            frames_dropped,
+            max_drift_frames: 0,
+            samples: 0,
+            drift_sum_frames: 0,
+        }
+    }
+
+    fn record(&mut self, target: usize, cur_frame: usize) {
+        let drift = target as i64 - cur_frame as i64;
+        self.samples += 1;
+        self.drift_sum_frames += drift;
+        if drift.abs() > self.max_drift_frames.abs() {
+            self.max_drift_frames = drift;
+        }
+    }
+
+    fn print_summary(&self) {
+        let avg = if self.samples > 0 {
+            self.drift_sum_frames as f64 / self.samples as f64
+        } else {
+            0.0
+        };
+        println!(
+            "[GFX Thread]: Drift summary: {} frames dropped to stay in sync, max drift {} frames, avg drift {:.2} frames",
+            self.frames_dropped.load(Ordering::Relaxed), self.max_drift_frames, avg
+        );
+    }
+}
+
+/// Highest quality-degradation level `QualityController` will back off to: 0 is full
+/// quality (Triangle filter, full resolution), 1 switches to the cheaper Nearest
+/// filter, 2 additionally decodes at half resolution before upscaling back up.
+const MAX_QUALITY_LEVEL: u8 = 2;
+
+/// Watches for sustained buffer underruns and degrades decode quality to let the
+/// image threads catch back up, then ramps quality back up once playback has been
+/// healthy for a while. The current level is communicated to the image threads via
+/// a shared `AtomicU8` so there's no need to restart them.
+struct QualityController {
+    quality: Arc<AtomicU8>,
+    recent_underruns: VecDeque<Instant>,
+    underrun_window: Duration,
+    underrun_threshold: usize,
+    healthy_since: Option<Instant>,
+    ramp_up_after: Duration,
+}
+
+impl QualityController {
+    fn new(quality: Arc<AtomicU8>) -> Self {
+        QualityController {
+            quality,
+            recent_underruns: VecDeque::new(),
+            underrun_window: Duration::from_secs(5),
+            underrun_threshold: 3,
+            healthy_since: None,
+            ramp_up_after: Duration::from_secs(10),
+        }
+    }
+
+    fn note_underrun(&mut self) {
+        let now = Instant::now();
+        self.recent_underruns.push_back(now);
+        while let Some(&front) = self.recent_underruns.front() {
+            if now.duration_since(front) > self.underrun_window {
+                self.recent_underruns.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.healthy_since = None;
+
+        if self.recent_underruns.len() >= self.underrun_threshold {
+            let current = self.quality.load(Ordering::Relaxed);
+            if current < MAX_QUALITY_LEVEL {
+                self.quality.store(current + 1, Ordering::Relaxed);
+                println!(
+                    "[GFX Thread]: {} underruns in the last {:?}, degrading quality to level {}",
+                    self.recent_underruns.len(), self.underrun_window, current + 1
+                );
+            }
+            self.recent_underruns.clear();
+        }
+    }
+
+    fn note_healthy(&mut self) {
+        let now = Instant::now();
+        let healthy_since = *self.healthy_since.get_or_insert(now);
+        if now.duration_since(healthy_since) >= self.ramp_up_after {
+            let current = self.quality.load(Ordering::Relaxed);
+            if current > 0 {
+                self.quality.store(current - 1, Ordering::Relaxed);
+                println!(
+                    "[GFX Thread]: Playback healthy for {:?}, raising quality to level {}",
+                    self.ramp_up_after, current - 1
+                );
+            }
+            self.healthy_since = Some(now);
+        }
+    }
+}
+
 fn millis() -> u128 {
     let start = SystemTime::now();
     let since_the_epoch = start
@@ -60,6 +199,8 @@ struct ImageThreadOptions {
     frame_fmt: String,
     thread_cnt: usize,
     thread_id: usize,
+    quality: Arc<AtomicU8>,
+    dropped_frames: Arc<AtomicUsize>,
 }
 
 fn start_img_thread(
@@ -73,6 +214,7 @@ fn start_img_thread(
             let begin_frame = cur_frame;
             let (begin_ms, mut io_us, mut conv_us, mut decode_us) = (millis(), 0, 0, 0);
             while tx.len() < opts.preload {
+                let quality_level = opts.quality.load(Ordering::Relaxed);
                 let mut last_us;
                 if cur_frame >= opts.frame_cnt {
                     break;
@@ -115,22 +257,39 @@ fn start_img_thread(
                 };
                 decode_us += micros() - last_us;
 
-                // Convert the image into a displayable format
+                // Convert the image into a displayable format; under sustained buffer
+                // underruns, the GFX thread has bumped `quality_level` to trade detail
+                // for decode/resize speed so the image threads can catch back up
                 last_us = micros();
-                let img_send = img_result
-                    .resize_exact(opts.disp_w, opts.disp_h, FilterType::Triangle)
-                    .to_rgb8();
+                let img_send = match quality_level {
+                    0 => img_result
+                        .resize_exact(opts.disp_w, opts.disp_h, FilterType::Triangle)
+                        .to_rgb8(),
+                    1 => img_result
+                        .resize_exact(opts.disp_w, opts.disp_h, FilterType::Nearest)
+                        .to_rgb8(),
+                    _ => {
+                        let half_w = (opts.disp_w / 2).max(1);
+                        let half_h = (opts.disp_h / 2).max(1);
+                        img_result
+                            .resize_exact(half_w, half_h, FilterType::Nearest)
+                            .resize_exact(opts.disp_w, opts.disp_h, FilterType::Nearest)
+                            .to_rgb8()
+                    }
+                };
                 conv_us += micros() - last_us;
 
                 tx.send(img_send).expect(&format!("[IMG Thread {}]: Failed to send image through channel?!", opts.thread_id).to_string());
                 cur_frame += 1;
             }
             println!(
-                "[IMG Thread {}]: Loaded frames {} to {}, took {}ms, io {}us, decode {}us, conversion {}us",
+                "[IMG Thread {}]: Loaded frames {} to {}, took {}ms, io {}us, decode {}us, conversion {}us, quality level {}, {} frames dropped total",
                 opts.thread_id,
                 begin_frame,
                 cur_frame,
-                millis() - begin_ms, io_us, decode_us, conv_us
+                millis() - begin_ms, io_us, decode_us, conv_us,
+                opts.quality.load(Ordering::Relaxed),
+                opts.dropped_frames.load(Ordering::Relaxed)
             );
             if tx.len() >= opts.preload {
                 std::thread::sleep(std::time::Duration::from_millis(100));
@@ -140,30 +299,26 @@ fn start_img_thread(
     })
 }
 
-fn play_audio(frame_dir: String) -> Result<(Sink, OutputStream), String> {
-    // Get the output stream
-    let stream_handle = match rodio::OutputStreamBuilder::open_default_stream() {
-        Ok(val) => val,
-        Err(err) => {
-            return Err(format!(
-                "Failed to open default audio stream!\nError: {:?}",
-                err
-            ))
-        }
-    };
-    // Load the sound file
-    let file = match File::open(format!("{}/music.mp3", frame_dir)) {
-        Ok(val) => val,
-        Err(err) => return Err(format!("Failed to open audio file!\nError: {:?}", err)),
-    };
-    // Create a sink for the device
-    let sink = rodio::Sink::connect_new(stream_handle.mixer());
-    // Decode and play the sound file
-    match Decoder::try_from(file) {
-        Ok(source) => stream_handle.mixer().add(source),
-        Err(err) => return Err(format!("Failed to play audio file!\nError: {:?}", err)),
-    };
-    Ok((sink, stream_handle))
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum AudioBackendKind {
+    /// The original rodio-based backend
+    Rodio,
+    /// Streams PCM through cpal, giving a sample-accurate position()
+    Cpal,
+    /// No real audio device - advances a synthetic clock, for headless/CI runs
+    Null,
+}
+
+fn make_audio_backend(
+    kind: AudioBackendKind,
+    source: AudioSource,
+    null_backend_duration: Duration,
+) -> Box<dyn AudioBackend> {
+    match kind {
+        AudioBackendKind::Rodio => Box::new(RodioBackend::new(source)),
+        AudioBackendKind::Cpal => Box::new(CpalBackend::new(source)),
+        AudioBackendKind::Null => Box::new(NullBackend::new(null_backend_duration)),
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -173,13 +328,23 @@ fn play_audio(frame_dir: String) -> Result<(Sink, OutputStream), String> {
     about = "A rust program for playing bad apple on a TFT display"
 )]
 struct Args {
-    /// Directory to grab frames/music from
+    /// Directory to grab frames/music from (frame input mode)
     #[arg(short, long, default_value = "/usr/share/bad-apple/")]
     directory: String,
 
-    /// The framerate to use, default of 60 is used
-    #[arg(long, default_value_t = 60)]
-    framerate: usize,
+    /// Whether to read pre-extracted frames + mp3 from `--directory`, or demux/decode
+    /// a single media file given via `--input-file`
+    #[arg(long, value_enum, default_value_t = InputMode::Frames)]
+    input_mode: InputMode,
+
+    /// Media file to demux/decode, required when `--input-mode container` is used
+    #[arg(long)]
+    input_file: Option<String>,
+
+    /// The framerate to use. Defaults to 60 in frame input mode; in container input
+    /// mode the demuxer's probed framerate is used unless this is set explicitly.
+    #[arg(long)]
+    framerate: Option<usize>,
 
     /// How many frames to preload, a zero value will use the framerate
     /// You should make sure at least a second of video is loaded continuously
@@ -187,21 +352,31 @@ struct Args {
     preload_frames: usize,
 
     /// Total number of frames, for the bad apple example this was exactly 6571
-    #[arg(long, default_value_t = 6571)]
-    total_frames: usize,
+    /// In container input mode this is optional: the demuxer reports the stream's
+    /// frame count, and this only needs to be set to override that.
+    #[arg(long)]
+    total_frames: Option<usize>,
 
     /// Initial delay (in milliseconds) to wait for the first round of frames to be preloaded
     /// This can be zero, but a non-zero value here lets the branch predictor to warm up
     #[arg(long, default_value_t = 500)]
     init_delay: u64,
 
-    /// Frame formate to use
+    /// Frame format to use, only meaningful in frame input mode
     #[arg(short, long, default_value = "jpg")]
     frame_format: String,
 
     // Image thread count, 0 will use the number of cores on the system
     #[arg(short, long, default_value_t = 0)]
     threads: usize,
+
+    /// How the GFX loop paces itself: against the audio clock, or a free-running wall-clock timer
+    #[arg(long, value_enum, default_value_t = SyncMode::Audio)]
+    sync: SyncMode,
+
+    /// Which AudioBackend implementation plays the decoded audio back
+    #[arg(long, value_enum, default_value_t = AudioBackendKind::Rodio)]
+    audio_backend: AudioBackendKind,
 }
 
 fn main() {
@@ -224,95 +399,318 @@ fn main() {
         gl.get_height()
     );
 
-    let total_frames = args.total_frames;
+    if matches!(args.input_mode, InputMode::Container) && args.input_file.is_none() {
+        eprintln!("[Main]: --input-file is required when --input-mode is container");
+        std::process::exit(1);
+    }
+
+    // the wallclock sync arm only ever reads the image channel, never pts_rx - in
+    // container mode the demuxer's video decode thread blocks forever on its bounded
+    // pts_tx channel once nobody drains it, freezing video a few hundred ms in while
+    // audio keeps playing. Audio-clock sync is what container mode's PTS plumbing was
+    // built for anyway, so just reject the combination instead of teaching wallclock
+    // mode to drain a channel it has no use for.
+    if matches!(args.input_mode, InputMode::Container) && matches!(args.sync, SyncMode::Wallclock) {
+        eprintln!("[Main]: --sync wallclock is not supported with --input-mode container (use --sync audio)");
+        std::process::exit(1);
+    }
+
+    // in container mode, probe the file for stream info up front so --total-frames and
+    // --frame-format can stay optional
+    let container_info = if let InputMode::Container = args.input_mode {
+        match demux::probe(args.input_file.as_ref().unwrap()) {
+            Ok(info) => Some(info),
+            Err(err) => {
+                eprintln!("[Main]: Failed to probe container: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // frame-directory mode has no way to probe how many frames exist, so it keeps the
+    // bad-apple-asset default; container mode falls back to "unknown" (driven instead
+    // by the decode channel closing) rather than inheriting that unrelated constant -
+    // Matroska in particular routinely omits a frame count
+    let total_frames = match args.input_mode {
+        InputMode::Frames => args.total_frames.unwrap_or(6571),
+        InputMode::Container => args
+            .total_frames
+            .or_else(|| container_info.as_ref().and_then(|info| info.video_frame_cnt))
+            .unwrap_or(usize::MAX),
+    };
+
+    // in container mode, drive frame timing off the demuxer's own probed framerate
+    // rather than assuming the CLI default of 60 applies to every clip; an explicit
+    // --framerate still wins, but gets flagged if it disagrees with the probe
+    let effective_framerate: f64 = match (args.framerate, &container_info) {
+        (Some(fr), Some(info)) if info.framerate > 0.0 && (fr as f64 - info.framerate).abs() > 0.5 => {
+            eprintln!(
+                "[Main]: Warning: --framerate {} does not match the container's probed framerate {:.3}, using --framerate",
+                fr, info.framerate
+            );
+            fr as f64
+        }
+        (Some(fr), _) => fr as f64,
+        (None, Some(info)) if info.framerate > 0.0 => info.framerate,
+        (None, _) => 60.0,
+    };
+
     let preload_frames = if args.preload_frames > 0 {
         args.preload_frames
     } else {
-        args.framerate
+        effective_framerate.round() as usize
     };
 
-    let img_thread_cnt = if args.threads == 0 {
-        thread::available_parallelism().unwrap().get()
-    } else {
-        args.threads
+    // a single demuxed container stream is decoded by one thread; striping it across
+    // several threads the way pre-extracted frames are would require seeking support
+    let img_thread_cnt = match args.input_mode {
+        InputMode::Frames => {
+            if args.threads == 0 {
+                thread::available_parallelism().unwrap().get()
+            } else {
+                args.threads
+            }
+        }
+        InputMode::Container => 1,
     };
 
     let scale_w = gl.get_width() as u32;
     let scale_h = gl.get_height() as u32;
 
+    // shared with the image threads so the adaptive quality backoff can steer their
+    // decode/resize work, and so their timing printouts can report on it
+    let quality_level = Arc::new(AtomicU8::new(0));
+    let dropped_frames = Arc::new(AtomicUsize::new(0));
+
     // allocate vecs for each image thread and it's channel'
     let mut img_rx_channels: Vec<channel::Receiver<image::ImageBuffer<image::Rgb<u8>, Vec<u8>>>> =
         Vec::with_capacity(img_thread_cnt);
     let mut img_thread_handles: Vec<Result<JoinHandle<()>, std::io::Error>> =
         Vec::with_capacity(img_thread_cnt);
-
-    // initialize an image threads for each channel
-    for id in 0..img_thread_cnt {
-        let (img_tx, img_rx) = channel::bounded::<RgbImage>(10);
-        img_rx_channels.push(img_rx);
-        img_thread_handles.push(start_img_thread(
-            ImageThreadOptions {
-                disp_w: scale_w,
-                disp_h: scale_h,
-                begin: 0,
-                frame_cnt: total_frames,
-                preload: preload_frames,
-                frame_dir: args.directory.clone(),
-                frame_fmt: args.frame_format.clone(),
-                thread_cnt: img_thread_cnt,
-                thread_id: id,
-            },
-            img_tx,
-        ));
+    // only populated in container mode, where the demuxer pushes each frame's real
+    // presentation timestamp in lockstep with the frame itself
+    let mut pts_rx: Option<channel::Receiver<f64>> = None;
+
+    match args.input_mode {
+        InputMode::Frames => {
+            // initialize an image threads for each channel
+            for id in 0..img_thread_cnt {
+                let (img_tx, img_rx) = channel::bounded::<RgbImage>(10);
+                img_rx_channels.push(img_rx);
+                img_thread_handles.push(start_img_thread(
+                    ImageThreadOptions {
+                        disp_w: scale_w,
+                        disp_h: scale_h,
+                        begin: 0,
+                        frame_cnt: total_frames,
+                        preload: preload_frames,
+                        frame_dir: args.directory.clone(),
+                        frame_fmt: args.frame_format.clone(),
+                        thread_cnt: img_thread_cnt,
+                        thread_id: id,
+                        quality: quality_level.clone(),
+                        dropped_frames: dropped_frames.clone(),
+                    },
+                    img_tx,
+                ));
+            }
+        }
+        InputMode::Container => {
+            let (img_tx, img_rx) = channel::bounded::<RgbImage>(10);
+            let (pts_tx, pts_rx_chan) = channel::bounded::<f64>(10);
+            img_rx_channels.push(img_rx);
+            pts_rx = Some(pts_rx_chan);
+            img_thread_handles.push(demux::spawn_video_decode_thread(
+                args.input_file.clone().unwrap(),
+                scale_w,
+                scale_h,
+                img_tx,
+                pts_tx,
+                quality_level.clone(),
+            ));
+        }
     }
 
     gl.clear(Color565::new(0, 0, 0));
     gl.push_buffer();
     std::thread::sleep(std::time::Duration::from_millis(args.init_delay));
 
-    let audio_result = play_audio(args.directory);
+    let audio_source = match args.input_mode {
+        InputMode::Frames => AudioSource::File(format!("{}/music.mp3", args.directory)),
+        InputMode::Container => {
+            const CONTAINER_SAMPLE_RATE: u32 = 48_000;
+            // decoding is left to whichever backend starts this source, so it can be
+            // done incrementally on its own producer thread instead of blocking main()
+            // on the whole track before playback can even begin
+            AudioSource::Container {
+                input_file: args.input_file.clone().unwrap(),
+                sample_rate: CONTAINER_SAMPLE_RATE,
+                channels: 2,
+            }
+        }
+    };
+
+    // the null backend needs to know how long to pretend to play for, since it has no
+    // real audio track to measure - approximate it off the video length, falling back
+    // to a generous runtime when the video length itself is unknown (container mode
+    // without a probed frame count)
+    let null_backend_duration = if total_frames == usize::MAX {
+        Duration::from_secs(3600)
+    } else {
+        Duration::from_secs_f64(total_frames as f64 / effective_framerate)
+    };
+    let mut audio_backend = make_audio_backend(args.audio_backend, audio_source, null_backend_duration);
+    let audio_start_result = audio_backend.start();
+    if let Err(err) = &audio_start_result {
+        eprintln!("[Main]: Failed to start audio backend: {}", err);
+    }
 
-    let frametime_ms = (1000 / args.framerate) as u128;
+    let frametime_ms = (1000.0 / effective_framerate) as u128;
     let mut cur_frame = 0;
-    println!("[GFX Thread]: Started!");
+    println!("[GFX Thread]: Started! (sync mode: {:?})", args.sync);
     let mut last_ms = 0;
+    let mut drift = DriftStats::new(dropped_frames.clone());
+    let mut quality_ctrl = QualityController::new(quality_level.clone());
+
+    // audio-master-clock sync is only possible if the audio backend actually started
+    let sync_mode = if matches!(args.sync, SyncMode::Audio) && audio_start_result.is_ok() {
+        SyncMode::Audio
+    } else {
+        SyncMode::Wallclock
+    };
+
+    // container mode pairs each frame with a real decoded PTS instead of an assumed
+    // constant framerate; holds a frame that's ready but not yet due to be shown
+    let mut pending_container_frame: Option<(f64, RgbImage)> = None;
+
     while cur_frame < total_frames {
-        let cur_ms = millis();
-        if cur_ms > last_ms + frametime_ms {
-            last_ms = cur_ms;
-
-            // a little over 60 fps
-            match img_rx_channels[cur_frame % img_thread_cnt].try_recv() {
-                Ok(img) => {
-                    //println!("[GFX Thread]: Drawing frame {}!", cur_frame);
-                    gl.draw_image_rgb(0, 0, &img);
-                    gl.push_buffer();
-                    cur_frame += 1;
+        match sync_mode {
+            SyncMode::Wallclock => {
+                let cur_ms = millis();
+                if cur_ms > last_ms + frametime_ms {
+                    last_ms = cur_ms;
+
+                    // a little over 60 fps
+                    match img_rx_channels[cur_frame % img_thread_cnt].try_recv() {
+                        Ok(img) => {
+                            //println!("[GFX Thread]: Drawing frame {}!", cur_frame);
+                            gl.draw_image_rgb(0, 0, &img);
+                            gl.push_buffer();
+                            cur_frame += 1;
+                            quality_ctrl.note_healthy();
+                        }
+                        Err(crossbeam::channel::TryRecvError::Empty) => {
+                            println!("[GFX Thread]: Buffer underrun, waiting 100ms to catch up!");
+                            quality_ctrl.note_underrun();
+                        }
+                        Err(crossbeam::channel::TryRecvError::Disconnected) => {
+                            println!(
+                                "[GFX Thread]: Decode channel closed, end of stream reached at frame {}",
+                                cur_frame
+                            );
+                            break;
+                        }
+                    };
+                } else {
+                    std::thread::sleep(std::time::Duration::from_millis(1));
                 }
-                Err(crossbeam::channel::TryRecvError::Empty) => {
-                    println!("[GFX Thread]: Buffer underrun, waiting 100ms to catch up!");
+            }
+            SyncMode::Audio if matches!(args.input_mode, InputMode::Container) => {
+                let audio_secs = audio_backend.position().as_secs_f64();
+                let target = (audio_secs * effective_framerate).floor() as usize;
+                drift.record(target, cur_frame);
+
+                // real per-frame PTS, not an assumed constant framerate, decides when
+                // a decoded frame is actually due - fetch one if we don't already have
+                // one in hand, then show it only once the audio clock reaches its PTS
+                if pending_container_frame.is_none() {
+                    match pts_rx.as_ref().unwrap().try_recv() {
+                        Ok(pts) => {
+                            let img = img_rx_channels[0]
+                                .recv()
+                                .expect("demux thread sends a frame right after its pts");
+                            pending_container_frame = Some((pts, img));
+                        }
+                        Err(crossbeam::channel::TryRecvError::Empty) => {
+                            println!("[GFX Thread]: Buffer underrun, waiting 100ms to catch up!");
+                            std::thread::sleep(std::time::Duration::from_millis(100));
+                            quality_ctrl.note_underrun();
+                        }
+                        Err(crossbeam::channel::TryRecvError::Disconnected) => {
+                            println!(
+                                "[GFX Thread]: Decode channel closed, end of stream reached at frame {}",
+                                cur_frame
+                            );
+                            break;
+                        }
+                    }
                 }
-                Err(err) => {
-                    eprintln!("[GFX Thread]: Encountered unknown error with channel!");
-                    eprintln!("[GFX Thread]: Error: {}", err);
-                    break;
+
+                match &pending_container_frame {
+                    Some((pts, _)) if *pts <= audio_secs => {
+                        let (_, img) = pending_container_frame.take().unwrap();
+                        gl.draw_image_rgb(0, 0, &img);
+                        gl.push_buffer();
+                        cur_frame += 1;
+                        quality_ctrl.note_healthy();
+                    }
+                    Some(_) => std::thread::sleep(std::time::Duration::from_millis(1)),
+                    None => {}
                 }
-            };
-        } else {
-            std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            SyncMode::Audio => {
+                let audio_secs = audio_backend.position().as_secs_f64();
+                let target = (audio_secs * effective_framerate).floor() as usize;
+                drift.record(target, cur_frame);
+
+                if target > cur_frame {
+                    // we're behind the audio clock: pull (and, if it's not the frame we're
+                    // about to land on, discard) from the round-robin channel that owns
+                    // cur_frame so the per-thread stripe stays aligned
+                    match img_rx_channels[cur_frame % img_thread_cnt].try_recv() {
+                        Ok(img) => {
+                            if target == cur_frame + 1 {
+                                gl.draw_image_rgb(0, 0, &img);
+                                gl.push_buffer();
+                            } else {
+                                drift.frames_dropped.fetch_add(1, Ordering::Relaxed);
+                            }
+                            cur_frame += 1;
+                            quality_ctrl.note_healthy();
+                        }
+                        Err(crossbeam::channel::TryRecvError::Empty) => {
+                            println!("[GFX Thread]: Buffer underrun, waiting 100ms to catch up!");
+                            std::thread::sleep(std::time::Duration::from_millis(100));
+                            quality_ctrl.note_underrun();
+                        }
+                        Err(crossbeam::channel::TryRecvError::Disconnected) => {
+                            println!(
+                                "[GFX Thread]: Decode channel closed, end of stream reached at frame {}",
+                                cur_frame
+                            );
+                            break;
+                        }
+                    }
+                } else {
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+            }
         }
     }
     println!("[GFX Thread]: Stopped!");
+    drift.print_summary();
     for img_handle in img_thread_handles {
         img_handle
             .expect("the thread has been built")
             .join()
             .unwrap();
     }
-    if let Ok((audio_sink, audio_stream)) = audio_result {
-        audio_sink.sleep_until_end();
-        drop(audio_stream);
+    if audio_start_result.is_ok() {
+        audio_backend.wait_until_end();
     }
+    audio_backend.stop();
 
     if gfx_mode.is_ok() {
         let _ = Framebuffer::set_kd_mode(KdMode::Text);